@@ -0,0 +1,121 @@
+use anyhow::{bail, Context, Result};
+use srt_tokio::{KeySettings, KeySize};
+use std::collections::HashMap;
+use std::fs;
+
+/// Whether a configured key is allowed to publish (act as an input) or
+/// subscribe (act as an output) to its stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+	Publish,
+	Subscribe,
+}
+
+/// Access-control table loaded from a config file, keyed by SRT StreamID and
+/// role. Each non-empty, non-comment line is `<stream_id> <role>
+/// [passphrase]`, where role is `publish` or `subscribe` and the passphrase
+/// is optional (an entry with no passphrase accepts unencrypted traffic). A
+/// configured passphrase is turned into AES-128 `KeySettings` by
+/// [`AuthConfig::authorize`], which is what actually gets the connection
+/// encrypted end to end.
+pub struct AuthConfig {
+	entries: HashMap<(String, Role), Option<String>>,
+}
+
+impl AuthConfig {
+	pub fn load(path: &str) -> Result<Self> {
+		let contents = fs::read_to_string(path).with_context(|| format!("Failed to read auth config: {path}"))?;
+		let mut entries = HashMap::new();
+
+		for (i, line) in contents.lines().enumerate() {
+			let line = line.trim();
+			if line.is_empty() || line.starts_with('#') {
+				continue;
+			}
+
+			let mut parts = line.split_whitespace();
+			let stream_id = parts.next().with_context(|| format!("{path}:{}: missing stream id", i + 1))?;
+			let role = match parts.next() {
+				Some("publish") => Role::Publish,
+				Some("subscribe") => Role::Subscribe,
+				other => bail!("{path}:{}: invalid role '{:?}', expected 'publish' or 'subscribe'", i + 1, other),
+			};
+			let passphrase = parts.next().map(str::to_string);
+
+			entries.insert((stream_id.to_string(), role), passphrase);
+		}
+
+		Ok(Self { entries })
+	}
+
+	/// Checks whether `stream_id` is allowed to act in `role`, returning the
+	/// `KeySettings` to present to `SrtSocket`/`ConnectionRequest` so the
+	/// connection is AES-encrypted (`None` for an entry with no configured
+	/// passphrase, which accepts unencrypted traffic), or the reason to
+	/// reject it.
+	pub fn authorize(&self, stream_id: &str, role: Role) -> std::result::Result<Option<KeySettings>, String> {
+		match self.entries.get(&(stream_id.to_string(), role)) {
+			Some(Some(passphrase)) => {
+				let passphrase = passphrase
+					.clone()
+					.try_into()
+					.map_err(|_| format!("configured passphrase for stream '{stream_id}' is invalid (must be 10-79 characters)"))?;
+				Ok(Some(KeySettings { key_size: KeySize::AES128, passphrase }))
+			}
+			Some(None) => Ok(None),
+			None => Err(format!("stream '{stream_id}' is not authorized for {role:?}")),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn write_config(name: &str, contents: &str) -> String {
+		let path = std::env::temp_dir().join(format!("srt-relay-auth-test-{name}-{}.conf", std::process::id()));
+		fs::write(&path, contents).unwrap();
+		path.to_str().unwrap().to_string()
+	}
+
+	#[test]
+	fn loads_roles_and_optional_passphrase() {
+		let path = write_config(
+			"basic",
+			"# comment\n\ncam1 publish supersecretpass\nviewer1 subscribe\n",
+		);
+		let config = AuthConfig::load(&path).unwrap();
+
+		assert!(config.authorize("cam1", Role::Publish).unwrap().is_some());
+		assert!(config.authorize("viewer1", Role::Subscribe).unwrap().is_none());
+
+		fs::remove_file(path).unwrap();
+	}
+
+	#[test]
+	fn rejects_invalid_role() {
+		let path = write_config("badrole", "cam1 broadcast\n");
+		assert!(AuthConfig::load(&path).is_err());
+		fs::remove_file(path).unwrap();
+	}
+
+	#[test]
+	fn authorize_rejects_unlisted_stream() {
+		let path = write_config("unlisted", "cam1 publish\n");
+		let config = AuthConfig::load(&path).unwrap();
+
+		assert!(config.authorize("cam2", Role::Publish).is_err());
+
+		fs::remove_file(path).unwrap();
+	}
+
+	#[test]
+	fn authorize_rejects_passphrase_outside_length_bounds() {
+		let path = write_config("shortpass", "cam1 publish tooshort\n");
+		let config = AuthConfig::load(&path).unwrap();
+
+		assert!(config.authorize("cam1", Role::Publish).is_err());
+
+		fs::remove_file(path).unwrap();
+	}
+}
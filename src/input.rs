@@ -0,0 +1,233 @@
+use crate::auth::{AuthConfig, Role};
+use crate::metrics::{ConnectionHandle, Direction, MetricsRegistry};
+use crate::router::StreamRouter;
+use crate::settings::SettingsConfig;
+use anyhow::{anyhow, bail, Context, Result};
+use futures::{SinkExt, StreamExt};
+use srt_tokio::SrtSocket;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+/// Initial backoff before the first reconnect attempt in caller mode.
+const RECONNECT_BACKOFF_MIN: Duration = Duration::from_millis(200);
+
+/// Backoff is doubled after each failed attempt, up to this cap.
+const RECONNECT_BACKOFF_MAX: Duration = Duration::from_secs(5);
+
+/// How the relay should obtain its input stream: either waiting for a
+/// publisher to push in (`listen://`), or dialing out to an upstream source
+/// and pulling from it (`call://`).
+pub enum InputMode {
+	Listen(SocketAddr),
+	/// `addr` is kept as a `host:port` string rather than resolved eagerly,
+	/// since the upstream is typically a hostname and may change address
+	/// between reconnects (DNS failover, container rescheduling, etc).
+	Call { addr: String, stream_id: String },
+}
+
+/// Parses the input address argument, which accepts a `listen://` or
+/// `call://` prefix to select the mode. A bare `host:port` with no prefix is
+/// treated as `listen://` for backwards compatibility. `call://` addresses
+/// may carry the upstream StreamID after the address, e.g.
+/// `call://origin.example:9000/mystream`. The `call://` host is resolved at
+/// dial time (see [`resolve`]) rather than here, so DNS names are accepted.
+pub fn parse_input_addr(raw: &str) -> Result<InputMode> {
+	if let Some(rest) = raw.strip_prefix("listen://") {
+		let addr = rest.parse().with_context(|| format!("Failed to parse listen address: {rest}"))?;
+		return Ok(InputMode::Listen(addr));
+	}
+
+	if let Some(rest) = raw.strip_prefix("call://") {
+		let (addr, stream_id) = match rest.split_once('/') {
+			Some((addr, stream_id)) => (addr, stream_id.to_string()),
+			None => (rest, String::new()),
+		};
+		if addr.is_empty() {
+			bail!("call:// address must not be empty");
+		}
+		return Ok(InputMode::Call { addr: addr.to_string(), stream_id });
+	}
+
+	let addr = raw.parse().with_context(|| format!("Failed to parse input address: {raw}"))?;
+	Ok(InputMode::Listen(addr))
+}
+
+/// Resolves a `host:port` string to a `SocketAddr`, taking the first result.
+/// Used at dial time (rather than once at startup) so a caller reconnect
+/// picks up DNS changes.
+async fn resolve(addr: &str) -> Result<SocketAddr> {
+	tokio::net::lookup_host(addr)
+		.await
+		.with_context(|| format!("Failed to resolve call address: {addr}"))?
+		.next()
+		.with_context(|| format!("No addresses found for call address: {addr}"))
+}
+
+/// Dials `addr` as an SRT caller and feeds received packets into the router
+/// under `stream_id`, exactly as a pushed-in input connection would.
+/// Reconnects with exponential backoff whenever the connection fails or the
+/// upstream stream ends, and stops cleanly once shutdown is signalled.
+///
+/// When `auth` is configured, the upstream is authorized as a publisher for
+/// `stream_id` up front (once, not per reconnect) and the resulting
+/// `KeySettings` are presented on every dial attempt, so a `call://` source
+/// can be pulled from an encrypted upstream the same as a pushed-in one.
+pub async fn run_caller(
+	addr: String,
+	stream_id: String,
+	router: StreamRouter,
+	mut shutdown_rx: watch::Receiver<bool>,
+	metrics: MetricsRegistry,
+	settings: Arc<SettingsConfig>,
+	auth: Option<Arc<AuthConfig>>,
+) -> Result<()> {
+	let mut backoff = RECONNECT_BACKOFF_MIN;
+	let stream_settings = settings.for_stream(&stream_id);
+
+	let key_settings = match &auth {
+		Some(auth) => auth
+			.authorize(&stream_id, Role::Publish)
+			.map_err(|reason| anyhow!("Not authorized to pull upstream input for stream '{}': {}", stream_id, reason))?,
+		None => None,
+	};
+
+	while !crate::shutdown::is_shutdown(&shutdown_rx) {
+		info!("Dialing upstream input {} for stream '{}'", addr, stream_id);
+
+		match resolve(&addr).await {
+			Ok(resolved) => {
+				let mut builder = SrtSocket::builder().latency(stream_settings.latency);
+				if let Some(key_settings) = key_settings.clone() {
+					builder = builder.encryption(key_settings);
+				}
+
+				match builder.call(resolved, Some(stream_id.as_str())).await {
+					Ok(socket) => {
+						info!("Connected to upstream input {} ({})", addr, resolved);
+						backoff = RECONNECT_BACKOFF_MIN;
+
+						let tx = router.register_input(&stream_id, stream_settings.capacity).await;
+						let conn_handle = metrics.track(stream_id.clone(), resolved, Direction::Input).await;
+						let result = process_caller_stream(socket, resolved, tx, shutdown_rx.clone(), conn_handle).await;
+						router.unregister_input(&stream_id).await;
+
+						if let Err(e) = result {
+							warn!("Upstream input {} stream ended: {}", addr, e);
+						} else {
+							warn!("Upstream input {} stream ended", addr);
+						}
+					}
+					Err(e) => {
+						error!("Failed to connect to upstream input {} ({}): {}", addr, resolved, e);
+					}
+				}
+			}
+			Err(e) => {
+				error!("Failed to resolve upstream input {}: {}", addr, e);
+			}
+		}
+
+		if crate::shutdown::is_shutdown(&shutdown_rx) {
+			break;
+		}
+
+		info!("Reconnecting to upstream input {} in {:?}", addr, backoff);
+		tokio::select! {
+			_ = tokio::time::sleep(backoff) => {}
+			_ = shutdown_rx.changed() => break,
+		}
+		backoff = (backoff * 2).min(RECONNECT_BACKOFF_MAX);
+	}
+
+	Ok(())
+}
+
+async fn process_caller_stream(
+	mut socket: SrtSocket,
+	addr: SocketAddr,
+	tx: std::sync::Arc<tokio::sync::broadcast::Sender<bytes::Bytes>>,
+	mut shutdown_rx: watch::Receiver<bool>,
+	conn: ConnectionHandle,
+) -> Result<()> {
+	loop {
+		tokio::select! {
+			result = socket.next() => {
+				let Some(result) = result else { break };
+				match result {
+					Ok((_, packet)) => {
+						conn.stats.record_packet(packet.len());
+						match tx.send(packet) {
+							Ok(_) => {}
+							Err(_) => tracing::debug!("No active receivers for broadcast"),
+						}
+					},
+					Err(e) => {
+						return Err(anyhow!("Error receiving packet from upstream {}: {}", addr, e));
+					}
+				}
+			}
+			_ = shutdown_rx.changed() => {
+				info!("Draining upstream input connection from {}", addr);
+				break;
+			}
+		}
+	}
+
+	let _ = socket.close().await;
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_listen_prefix() {
+		let mode = parse_input_addr("listen://0.0.0.0:10001").unwrap();
+		assert!(matches!(mode, InputMode::Listen(addr) if addr.to_string() == "0.0.0.0:10001"));
+	}
+
+	#[test]
+	fn bare_host_port_defaults_to_listen_mode() {
+		let mode = parse_input_addr("0.0.0.0:10001").unwrap();
+		assert!(matches!(mode, InputMode::Listen(_)));
+	}
+
+	#[test]
+	fn parses_call_prefix_with_hostname_and_stream_id() {
+		let mode = parse_input_addr("call://origin.example:9000/mystream").unwrap();
+		match mode {
+			InputMode::Call { addr, stream_id } => {
+				assert_eq!(addr, "origin.example:9000");
+				assert_eq!(stream_id, "mystream");
+			}
+			_ => panic!("expected InputMode::Call"),
+		}
+	}
+
+	#[test]
+	fn parses_call_prefix_without_stream_id() {
+		let mode = parse_input_addr("call://origin.example:9000").unwrap();
+		match mode {
+			InputMode::Call { addr, stream_id } => {
+				assert_eq!(addr, "origin.example:9000");
+				assert_eq!(stream_id, "");
+			}
+			_ => panic!("expected InputMode::Call"),
+		}
+	}
+
+	#[test]
+	fn rejects_empty_call_address() {
+		assert!(parse_input_addr("call:///mystream").is_err());
+	}
+
+	#[test]
+	fn rejects_unparseable_listen_address() {
+		assert!(parse_input_addr("listen://not-an-address").is_err());
+	}
+}
@@ -1,159 +1,403 @@
+mod auth;
+mod input;
+mod metrics;
+mod router;
+mod settings;
+mod shutdown;
+
 use anyhow::{Context, Result};
+use auth::{AuthConfig, Role};
 use bytes::Bytes;
 use futures::{SinkExt, StreamExt};
+use input::{parse_input_addr, InputMode};
+use metrics::{Direction, MetricsRegistry};
+use router::StreamRouter;
+use settings::{DropPolicy, SettingsConfig};
+use shutdown::Shutdown;
 use srt_tokio::{SrtListener, SrtSocket};
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinSet;
 use tracing::{debug, error, info, warn};
 
+/// StreamID used for connections that don't present one, so the relay still
+/// has a single default stream to route through.
+const DEFAULT_STREAM_ID: &str = "";
+
 #[tokio::main]
 async fn main() -> Result<()> {
 	tracing_subscriber::fmt::init();
 
 	let args: Vec<String> = std::env::args().collect();
-	if args.len() != 3 {
-		eprintln!("Usage: {} <input_address> <output_address>", args[0]);
-		eprintln!("Example: {} 0.0.0.0:10001 0.0.0.0:11001", args[0]);
+	if args.len() < 3 || args.len() > 6 {
+		eprintln!("Usage: {} <input_address> <output_address> [auth_config|-] [metrics_address|-] [stream_settings_config]", args[0]);
+		eprintln!("Example: {} listen://0.0.0.0:10001 0.0.0.0:11001", args[0]);
+		eprintln!("Example: {} call://origin.example:9000/mystream 0.0.0.0:11001 auth.conf 0.0.0.0:9090 streams.conf", args[0]);
 		std::process::exit(1);
 	}
 
-	let input_addr: SocketAddr = args[1].parse().with_context(|| format!("Failed to parse input address: {}", args[1]))?;
+	let input_mode = parse_input_addr(&args[1])?;
 
 	let output_addr: SocketAddr = args[2].parse().with_context(|| format!("Failed to parse output address: {}", args[2]))?;
 
+	let auth = match args.get(3).map(String::as_str) {
+		Some(path) if path != "-" => {
+			info!("Loading auth config from {}", path);
+			Some(Arc::new(AuthConfig::load(path)?))
+		}
+		_ => {
+			warn!("No auth config provided, accepting any publisher/subscriber unauthenticated");
+			None
+		}
+	};
+
+	let metrics_addr = match args.get(4).map(String::as_str) {
+		Some(addr) if addr != "-" => Some(addr.parse().with_context(|| format!("Failed to parse metrics address: {addr}"))?),
+		_ => None,
+	};
+
+	let settings = match args.get(5) {
+		Some(path) => {
+			info!("Loading stream settings from {}", path);
+			Arc::new(SettingsConfig::load(path)?)
+		}
+		None => Arc::new(SettingsConfig::default()),
+	};
+
 	info!("Starting srt-relay server");
-	info!("Input socket: {}", input_addr);
 	info!("Output socket: {}", output_addr);
 
-	let (tx, _) = broadcast::channel::<Bytes>(1024);
-	let tx = Arc::new(tx);
+	let router = StreamRouter::new();
+	let metrics_registry = MetricsRegistry::new();
+
+	let (shutdown, shutdown_rx) = Shutdown::new();
+	tokio::spawn(shutdown.listen());
+
+	if let Some(metrics_addr) = metrics_addr {
+		let router_metrics = router.clone();
+		let metrics_for_serve = metrics_registry.clone();
+		let shutdown_rx_metrics = shutdown_rx.clone();
+		tokio::spawn(async move {
+			if let Err(e) = metrics::serve(metrics_addr, metrics_for_serve, router_metrics, shutdown_rx_metrics).await {
+				error!("Metrics handler error: {}", e);
+			}
+		});
+	}
 
-	let tx_input = Arc::clone(&tx);
+	let router_input = router.clone();
+	let shutdown_rx_input = shutdown_rx.clone();
+	let auth_input = auth.clone();
+	let metrics_input = metrics_registry.clone();
+	let settings_input = settings.clone();
 	let input_task = tokio::spawn(async move {
-		if let Err(e) = handle_input(input_addr, tx_input).await {
+		let result = match input_mode {
+			InputMode::Listen(addr) => {
+				info!("Input socket (listen): {}", addr);
+				handle_input(addr, router_input, shutdown_rx_input, auth_input, metrics_input, settings_input).await
+			}
+			InputMode::Call { addr, stream_id } => {
+				info!("Input socket (call): {} stream '{}'", addr, stream_id);
+				input::run_caller(addr, stream_id, router_input, shutdown_rx_input, metrics_input, settings_input, auth_input).await
+			}
+		};
+		if let Err(e) = result {
 			error!("Input handler error: {}", e);
 		}
 	});
 
+	let mut shutdown_rx_main = shutdown_rx.clone();
 	let output_task = tokio::spawn(async move {
-		if let Err(e) = handle_output(output_addr, tx).await {
+		if let Err(e) = handle_output(output_addr, router, shutdown_rx, auth, metrics_registry, settings).await {
 			error!("Output handler error: {}", e);
 		}
 	});
 
-	let _ = tokio::join!(input_task, output_task);
+	let tasks = async { let _ = tokio::join!(input_task, output_task); };
+	tokio::pin!(tasks);
+	tokio::select! {
+		_ = &mut tasks => return Ok(()),
+		_ = shutdown_rx_main.changed() => {}
+	}
+
+	if tokio::time::timeout(shutdown::DRAIN_TIMEOUT, tasks).await.is_err() {
+		warn!("Timed out waiting for connections to drain, shutting down anyway");
+	}
 
 	Ok(())
 }
 
-async fn handle_input(addr: SocketAddr, tx: Arc<broadcast::Sender<Bytes>>) -> Result<()> {
-	let (_listener, mut incoming) = SrtListener::builder().bind(addr).await.context("Failed to bind input SRT listener")?;
+/// Warns, once per StreamID per listener, when `stream_id`'s configured
+/// latency would have differed from `settings.default_settings().latency` —
+/// the listener has already bound with the default, so a per-stream
+/// `latency_ms=` only takes effect in `call://` mode. `warned` tracks which
+/// streams have already triggered the warning on this listener, so a busy
+/// stream doesn't spam the log on every accept.
+fn warn_if_latency_ignored(settings: &SettingsConfig, stream_id: &str, listener_addr: SocketAddr, warned: &mut std::collections::HashSet<String>) {
+	let configured_latency = settings.for_stream(stream_id).latency;
+	if configured_latency != settings.default_settings().latency && warned.insert(stream_id.to_string()) {
+		warn!(
+			"Stream '{}' configures latency_ms={:?}, but the listener on {} is already bound with the default latency; per-stream latency only applies in call:// mode",
+			stream_id, configured_latency, listener_addr
+		);
+	}
+}
+
+async fn handle_input(
+	addr: SocketAddr,
+	router: StreamRouter,
+	mut shutdown_rx: watch::Receiver<bool>,
+	auth: Option<Arc<AuthConfig>>,
+	metrics: MetricsRegistry,
+	settings: Arc<SettingsConfig>,
+) -> Result<()> {
+	let (_listener, mut incoming) = SrtListener::builder()
+		.latency(settings.default_settings().latency)
+		.bind(addr)
+		.await
+		.context("Failed to bind input SRT listener")?;
 
 	info!("Input listener ready on {}", addr);
 
-	while let Some(request) = incoming.incoming().next().await {
-		let peer_addr = request.remote();
-		info!("Input connection request from {}", peer_addr);
+	let mut connections = JoinSet::new();
+	let mut latency_warned: std::collections::HashSet<String> = std::collections::HashSet::new();
+
+	loop {
+		tokio::select! {
+			request = incoming.incoming().next() => {
+				let Some(request) = request else { break };
+				let peer_addr = request.remote();
+				let stream_id = request.stream_id().map(ToString::to_string).unwrap_or_else(|| DEFAULT_STREAM_ID.to_string());
+				info!("Input connection request from {} for stream '{}'", peer_addr, stream_id);
+
+				let key_settings = match &auth {
+					Some(auth) => match auth.authorize(&stream_id, Role::Publish) {
+						Ok(key_settings) => key_settings,
+						Err(reason) => {
+							warn!("Rejecting input connection from {}: {}", peer_addr, reason);
+							continue;
+						}
+					},
+					None => None,
+				};
+
+				warn_if_latency_ignored(&settings, &stream_id, addr, &mut latency_warned);
 
-		match request.accept(None).await {
-			Ok(socket) => {
-				info!("Input connection accepted from {}", peer_addr);
+				match request.accept(key_settings).await {
+					Ok(socket) => {
+						info!("Input connection accepted from {} for stream '{}'", peer_addr, stream_id);
 
-				let tx = Arc::clone(&tx);
-				tokio::spawn(async move {
-					if let Err(e) = process_input_stream(socket, peer_addr, tx).await {
-						error!("Error processing input from {}: {}", peer_addr, e);
+						let tx = router.register_input(&stream_id, settings.for_stream(&stream_id).capacity).await;
+						let router = router.clone();
+						let conn_shutdown_rx = shutdown_rx.clone();
+						let conn_handle = metrics.track(stream_id.clone(), peer_addr, Direction::Input).await;
+						connections.spawn(async move {
+							if let Err(e) = process_input_stream(socket, peer_addr, tx, conn_shutdown_rx, conn_handle).await {
+								error!("Error processing input from {}: {}", peer_addr, e);
+							}
+							router.unregister_input(&stream_id).await;
+							info!("Input connection from {} closed", peer_addr);
+						});
 					}
-					info!("Input connection from {} closed", peer_addr);
-				});
+					Err(e) => {
+						error!("Failed to accept connection from {}: {}", peer_addr, e);
+					}
+				}
 			}
-			Err(e) => {
-				error!("Failed to accept connection from {}: {}", peer_addr, e);
+			_ = shutdown_rx.changed() => {
+				info!("Input listener on {} shutting down", addr);
+				break;
 			}
 		}
 	}
 
+	while connections.join_next().await.is_some() {}
+
 	Ok(())
 }
 
-async fn process_input_stream(mut socket: SrtSocket, peer_addr: SocketAddr, tx: Arc<broadcast::Sender<Bytes>>) -> Result<()> {
-	while let Some(result) = socket.next().await {
-		match result {
-			Ok((_, packet)) => {
-				let packet_size = packet.len();
-				debug!("Received {} bytes from {}", packet_size, peer_addr);
+async fn process_input_stream(
+	mut socket: SrtSocket,
+	peer_addr: SocketAddr,
+	tx: Arc<broadcast::Sender<Bytes>>,
+	mut shutdown_rx: watch::Receiver<bool>,
+	conn: metrics::ConnectionHandle,
+) -> Result<()> {
+	loop {
+		tokio::select! {
+			result = socket.next() => {
+				let Some(result) = result else { break };
+				match result {
+					Ok((_, packet)) => {
+						let packet_size = packet.len();
+						debug!("Received {} bytes from {}", packet_size, peer_addr);
+						conn.stats.record_packet(packet_size);
 
-				match tx.send(packet) {
-					Ok(count) => {
-						debug!("Broadcasted packet to {} receivers", count);
+						match tx.send(packet) {
+							Ok(count) => {
+								debug!("Broadcasted packet to {} receivers", count);
+							}
+							Err(_) => {
+								debug!("No active receivers for broadcast");
+							}
+						}
 					}
-					Err(_) => {
-						debug!("No active receivers for broadcast");
+					Err(e) => {
+						warn!("Error receiving packet from {}: {}", peer_addr, e);
+						return Err(e.into());
 					}
 				}
 			}
-			Err(e) => {
-				warn!("Error receiving packet from {}: {}", peer_addr, e);
-				return Err(e.into());
+			_ = shutdown_rx.changed() => {
+				info!("Draining input connection from {}", peer_addr);
+				break;
 			}
 		}
 	}
 
+	let _ = socket.close().await;
+
 	Ok(())
 }
 
-async fn handle_output(addr: SocketAddr, tx: Arc<broadcast::Sender<Bytes>>) -> Result<()> {
-	let (_listener, mut incoming) = SrtListener::builder().bind(addr).await.context("Failed to bind output SRT listener")?;
+async fn handle_output(
+	addr: SocketAddr,
+	router: StreamRouter,
+	mut shutdown_rx: watch::Receiver<bool>,
+	auth: Option<Arc<AuthConfig>>,
+	metrics: MetricsRegistry,
+	settings: Arc<SettingsConfig>,
+) -> Result<()> {
+	let (_listener, mut incoming) = SrtListener::builder()
+		.latency(settings.default_settings().latency)
+		.bind(addr)
+		.await
+		.context("Failed to bind output SRT listener")?;
 
 	info!("Output listener ready on {}", addr);
 
-	while let Some(request) = incoming.incoming().next().await {
-		let peer_addr = request.remote();
-		info!("Output connection request from {}", peer_addr);
+	let mut connections = JoinSet::new();
+	let mut latency_warned: std::collections::HashSet<String> = std::collections::HashSet::new();
+
+	loop {
+		tokio::select! {
+			request = incoming.incoming().next() => {
+				let Some(request) = request else { break };
+				let peer_addr = request.remote();
+				let stream_id = request.stream_id().map(ToString::to_string).unwrap_or_else(|| DEFAULT_STREAM_ID.to_string());
+				info!("Output connection request from {} for stream '{}'", peer_addr, stream_id);
+
+				let key_settings = match &auth {
+					Some(auth) => match auth.authorize(&stream_id, Role::Subscribe) {
+						Ok(key_settings) => key_settings,
+						Err(reason) => {
+							warn!("Rejecting output connection from {}: {}", peer_addr, reason);
+							continue;
+						}
+					},
+					None => None,
+				};
 
-		match request.accept(None).await {
-			Ok(socket) => {
-				info!("Output connection accepted from {}", peer_addr);
+				warn_if_latency_ignored(&settings, &stream_id, addr, &mut latency_warned);
 
-				let rx = tx.subscribe();
+				match request.accept(key_settings).await {
+					Ok(socket) => {
+						info!("Output connection accepted from {} for stream '{}'", peer_addr, stream_id);
 
-				tokio::spawn(async move {
-					if let Err(e) = process_output_stream(socket, peer_addr, rx).await {
-						error!("Error processing output for {}: {}", peer_addr, e);
+						let stream_settings = settings.for_stream(&stream_id);
+						let rx = router.subscribe(&stream_id, stream_settings.capacity).await;
+						let router = router.clone();
+						let conn_shutdown_rx = shutdown_rx.clone();
+						let conn_handle = metrics.track(stream_id.clone(), peer_addr, Direction::Output).await;
+						connections.spawn(async move {
+							if let Err(e) = process_output_stream(socket, peer_addr, rx, conn_shutdown_rx, conn_handle, stream_settings.drop_policy).await {
+								error!("Error processing output for {}: {}", peer_addr, e);
+							}
+							router.unsubscribe(&stream_id).await;
+							info!("Output connection to {} closed", peer_addr);
+						});
+					}
+					Err(e) => {
+						error!("Failed to accept connection from {}: {}", peer_addr, e);
 					}
-					info!("Output connection to {} closed", peer_addr);
-				});
+				}
 			}
-			Err(e) => {
-				error!("Failed to accept connection from {}: {}", peer_addr, e);
+			_ = shutdown_rx.changed() => {
+				info!("Output listener on {} shutting down", addr);
+				break;
 			}
 		}
 	}
 
+	while connections.join_next().await.is_some() {}
+
 	Ok(())
 }
 
-async fn process_output_stream(mut socket: SrtSocket, peer_addr: SocketAddr, mut rx: broadcast::Receiver<Bytes>) -> Result<()> {
+async fn process_output_stream(
+	mut socket: SrtSocket,
+	peer_addr: SocketAddr,
+	mut rx: broadcast::Receiver<Bytes>,
+	mut shutdown_rx: watch::Receiver<bool>,
+	conn: metrics::ConnectionHandle,
+	drop_policy: DropPolicy,
+) -> Result<()> {
+	let started_at = std::time::Instant::now();
+
 	loop {
-		match rx.recv().await {
-			Ok(packet) => {
-				let packet_size = packet.len();
+		tokio::select! {
+			result = rx.recv() => {
+				match result {
+					Ok(packet) => {
+						let packet_size = packet.len();
 
-				if let Err(e) = socket.send((std::time::Instant::now(), packet)).await {
-					warn!("Failed to send packet to {}: {}", peer_addr, e);
-					return Err(e.into());
-				}
+						if let Err(e) = socket.send((std::time::Instant::now(), packet)).await {
+							warn!("Failed to send packet to {}: {}", peer_addr, e);
+							return Err(e.into());
+						}
 
-				debug!("Sent {} bytes to {}", packet_size, peer_addr);
-			}
-			Err(broadcast::error::RecvError::Lagged(count)) => {
-				warn!("Output {} lagged by {} messages", peer_addr, count);
+						conn.stats.record_packet(packet_size);
+						debug!("Sent {} bytes to {}", packet_size, peer_addr);
+					}
+					Err(broadcast::error::RecvError::Lagged(count)) => {
+						conn.stats.record_lagged(count);
+						warn!("Output {} lagged by {} messages", peer_addr, count);
+
+						match drop_policy {
+							// The broadcast channel has already resynced this receiver to the
+							// newest packet; the `warn!` above (surfaced in `lagged` via
+							// metrics) is the discontinuity signal. Nothing is injected into
+							// the payload stream itself, since an out-of-band consumer of the
+							// SRT stream has no way to interpret an inline marker as anything
+							// but corrupt media.
+							DropPolicy::Resync => {}
+							DropPolicy::DisconnectOnLag { threshold } if count >= threshold => {
+								warn!("Output {} exceeded lag threshold ({} >= {}), disconnecting", peer_addr, count, threshold);
+								break;
+							}
+							DropPolicy::DisconnectOnLag { .. } => {}
+						}
+					}
+					Err(broadcast::error::RecvError::Closed) => {
+						info!("Broadcast channel closed for {}", peer_addr);
+						break;
+					}
+				}
 			}
-			Err(broadcast::error::RecvError::Closed) => {
-				info!("Broadcast channel closed for {}", peer_addr);
-				return Ok(());
+			_ = shutdown_rx.changed() => {
+				info!("Draining output connection to {}", peer_addr);
+				break;
 			}
 		}
 	}
+
+	let lagged = conn.stats.lagged();
+	if lagged > 0 {
+		let rate = lagged as f64 / started_at.elapsed().as_secs_f64().max(1.0);
+		info!("Output {} sustained lag rate: {:.2} dropped packets/sec ({} total)", peer_addr, rate, lagged);
+	}
+
+	let _ = socket.close().await;
+
+	Ok(())
 }
@@ -0,0 +1,210 @@
+use crate::router::StreamRouter;
+use anyhow::{Context, Result};
+use futures::SinkExt;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{watch, RwLock};
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, info, warn};
+
+/// How often a fresh snapshot is pushed to connected metrics clients.
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Which side of the relay a tracked connection belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+	Input,
+	Output,
+}
+
+impl Direction {
+	fn as_str(self) -> &'static str {
+		match self {
+			Direction::Input => "input",
+			Direction::Output => "output",
+		}
+	}
+}
+
+/// Per-connection counters, updated from the hot path with relaxed atomics
+/// since they're only ever read back for a periodic snapshot.
+#[derive(Default)]
+pub struct ConnectionStats {
+	bytes: AtomicU64,
+	packets: AtomicU64,
+	lagged: AtomicU64,
+}
+
+impl ConnectionStats {
+	pub fn record_packet(&self, size: usize) {
+		self.bytes.fetch_add(size as u64, Ordering::Relaxed);
+		self.packets.fetch_add(1, Ordering::Relaxed);
+	}
+
+	pub fn record_lagged(&self, count: u64) {
+		self.lagged.fetch_add(count, Ordering::Relaxed);
+	}
+
+	pub fn lagged(&self) -> u64 {
+		self.lagged.load(Ordering::Relaxed)
+	}
+}
+
+struct TrackedConnection {
+	stream_id: String,
+	peer_addr: SocketAddr,
+	direction: Direction,
+	stats: Arc<ConnectionStats>,
+}
+
+/// Registry of active connections' stats, periodically snapshotted and
+/// pushed to metrics clients as JSON alongside the router's live per-stream
+/// subscriber counts.
+#[derive(Clone, Default)]
+pub struct MetricsRegistry {
+	connections: Arc<RwLock<HashMap<u64, TrackedConnection>>>,
+	next_id: Arc<AtomicU64>,
+}
+
+/// Handle returned by [`MetricsRegistry::track`]; holds the stats to update
+/// from the connection's hot path and removes the connection from the
+/// registry when dropped.
+pub struct ConnectionHandle {
+	registry: MetricsRegistry,
+	id: u64,
+	pub stats: Arc<ConnectionStats>,
+}
+
+impl Drop for ConnectionHandle {
+	fn drop(&mut self) {
+		let registry = self.registry.clone();
+		let id = self.id;
+		tokio::spawn(async move {
+			registry.connections.write().await.remove(&id);
+		});
+	}
+}
+
+impl MetricsRegistry {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Registers a new connection and returns a handle carrying its stats.
+	pub async fn track(&self, stream_id: String, peer_addr: SocketAddr, direction: Direction) -> ConnectionHandle {
+		let stats = Arc::new(ConnectionStats::default());
+		let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+
+		self.connections
+			.write()
+			.await
+			.insert(id, TrackedConnection { stream_id, peer_addr, direction, stats: Arc::clone(&stats) });
+
+		ConnectionHandle { registry: self.clone(), id, stats }
+	}
+
+	async fn snapshot_json(&self, router: &StreamRouter) -> String {
+		let connections = self.connections.read().await;
+		let conn_entries: Vec<String> = connections
+			.values()
+			.map(|conn| {
+				format!(
+					r#"{{"stream_id":"{}","peer":"{}","direction":"{}","bytes":{},"packets":{},"lagged":{}}}"#,
+					json_escape(&conn.stream_id),
+					conn.peer_addr,
+					conn.direction.as_str(),
+					conn.stats.bytes.load(Ordering::Relaxed),
+					conn.stats.packets.load(Ordering::Relaxed),
+					conn.stats.lagged.load(Ordering::Relaxed),
+				)
+			})
+			.collect();
+
+		let stream_entries: Vec<String> = router
+			.subscriber_counts()
+			.await
+			.into_iter()
+			.map(|(stream_id, count)| format!(r#"{{"stream_id":"{}","subscribers":{}}}"#, json_escape(&stream_id), count))
+			.collect();
+
+		format!(r#"{{"connections":[{}],"streams":[{}]}}"#, conn_entries.join(","), stream_entries.join(","))
+	}
+}
+
+/// Escapes `s` for embedding in a JSON string literal. StreamIDs are set by
+/// the connecting peer (and, when no auth config is loaded, by anyone), so
+/// this can't assume they're free of quotes, backslashes, or control
+/// characters.
+fn json_escape(s: &str) -> String {
+	let mut out = String::with_capacity(s.len());
+	for c in s.chars() {
+		match c {
+			'"' => out.push_str("\\\""),
+			'\\' => out.push_str("\\\\"),
+			'\n' => out.push_str("\\n"),
+			'\r' => out.push_str("\\r"),
+			'\t' => out.push_str("\\t"),
+			c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+			c => out.push(c),
+		}
+	}
+	out
+}
+
+/// Serves metrics snapshots as JSON over WebSocket on `addr`, pushing a new
+/// snapshot to every connected client every [`SNAPSHOT_INTERVAL`].
+pub async fn serve(addr: SocketAddr, registry: MetricsRegistry, router: StreamRouter, mut shutdown_rx: watch::Receiver<bool>) -> Result<()> {
+	let listener = TcpListener::bind(addr).await.with_context(|| format!("Failed to bind metrics listener on {addr}"))?;
+
+	info!("Metrics listener ready on {}", addr);
+
+	loop {
+		tokio::select! {
+			accepted = listener.accept() => {
+				let (stream, peer_addr) = accepted.context("Failed to accept metrics client")?;
+				let registry = registry.clone();
+				let router = router.clone();
+				let conn_shutdown_rx = shutdown_rx.clone();
+				tokio::spawn(async move {
+					if let Err(e) = serve_client(stream, peer_addr, registry, router, conn_shutdown_rx).await {
+						warn!("Metrics client {} error: {}", peer_addr, e);
+					}
+				});
+			}
+			_ = shutdown_rx.changed() => {
+				info!("Metrics listener on {} shutting down", addr);
+				break;
+			}
+		}
+	}
+
+	Ok(())
+}
+
+async fn serve_client(stream: TcpStream, peer_addr: SocketAddr, registry: MetricsRegistry, router: StreamRouter, mut shutdown_rx: watch::Receiver<bool>) -> Result<()> {
+	let mut ws = tokio_tungstenite::accept_async(stream).await.context("WebSocket handshake with metrics client failed")?;
+	info!("Metrics client connected from {}", peer_addr);
+
+	let mut interval = tokio::time::interval(SNAPSHOT_INTERVAL);
+
+	loop {
+		tokio::select! {
+			_ = interval.tick() => {
+				let snapshot = registry.snapshot_json(&router).await;
+				if ws.send(Message::Text(snapshot)).await.is_err() {
+					break;
+				}
+			}
+			_ = shutdown_rx.changed() => break,
+		}
+	}
+
+	let _ = ws.close(None).await;
+	debug!("Metrics client {} disconnected", peer_addr);
+
+	Ok(())
+}
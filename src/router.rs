@@ -0,0 +1,150 @@
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::{broadcast, RwLock};
+
+/// A single routed stream: the broadcast channel carrying its packets plus a
+/// count of the input connections currently publishing into it, so we know
+/// when it's safe to drop the channel from the router.
+struct StreamChannel {
+	tx: broadcast::Sender<Bytes>,
+	input_count: AtomicUsize,
+}
+
+/// Routes packets by SRT StreamID, so a single relay process can multiplex
+/// many independent streams instead of mixing every input into one channel.
+#[derive(Clone)]
+pub struct StreamRouter {
+	channels: Arc<RwLock<HashMap<String, Arc<StreamChannel>>>>,
+}
+
+impl StreamRouter {
+	pub fn new() -> Self {
+		Self { channels: Arc::new(RwLock::new(HashMap::new())) }
+	}
+
+	/// Registers an input publisher for `stream_id`, creating the channel
+	/// with `capacity` if this is the first publisher or subscriber seen for
+	/// that key. `capacity` is ignored if the channel already exists.
+	pub async fn register_input(&self, stream_id: &str, capacity: usize) -> Arc<broadcast::Sender<Bytes>> {
+		let mut channels = self.channels.write().await;
+		let channel = channels
+			.entry(stream_id.to_string())
+			.or_insert_with(|| Arc::new(StreamChannel { tx: broadcast::channel(capacity).0, input_count: AtomicUsize::new(0) }));
+		channel.input_count.fetch_add(1, Ordering::SeqCst);
+
+		Arc::new(channel.tx.clone())
+	}
+
+	/// Unregisters an input publisher for `stream_id`, dropping the channel
+	/// once there are no publishers and no subscribers left for it.
+	pub async fn unregister_input(&self, stream_id: &str) {
+		let mut channels = self.channels.write().await;
+		let Some(channel) = channels.get(stream_id) else {
+			return;
+		};
+
+		if channel.input_count.fetch_sub(1, Ordering::SeqCst) == 1 && channel.tx.receiver_count() == 0 {
+			channels.remove(stream_id);
+		}
+	}
+
+	/// Subscribes an output connection to `stream_id`, lazily creating the
+	/// channel with `capacity` if no publisher has registered for it yet.
+	/// `capacity` is ignored if the channel already exists.
+	pub async fn subscribe(&self, stream_id: &str, capacity: usize) -> broadcast::Receiver<Bytes> {
+		let mut channels = self.channels.write().await;
+		let channel = channels
+			.entry(stream_id.to_string())
+			.or_insert_with(|| Arc::new(StreamChannel { tx: broadcast::channel(capacity).0, input_count: AtomicUsize::new(0) }));
+
+		channel.tx.subscribe()
+	}
+
+	/// Returns the current subscriber count for every active stream, keyed
+	/// by StreamID, for reporting in metrics snapshots.
+	pub async fn subscriber_counts(&self) -> HashMap<String, usize> {
+		let channels = self.channels.read().await;
+		channels.iter().map(|(stream_id, channel)| (stream_id.clone(), channel.tx.receiver_count())).collect()
+	}
+
+	/// Called once an output connection drops its subscription, so an
+	/// abandoned channel with no publisher and no subscribers is cleaned up.
+	pub async fn unsubscribe(&self, stream_id: &str) {
+		let mut channels = self.channels.write().await;
+		let Some(channel) = channels.get(stream_id) else {
+			return;
+		};
+
+		if channel.input_count.load(Ordering::SeqCst) == 0 && channel.tx.receiver_count() == 0 {
+			channels.remove(stream_id);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[tokio::test]
+	async fn unregister_drops_channel_once_idle() {
+		let router = StreamRouter::new();
+		router.register_input("s1", 8).await;
+		assert!(router.subscriber_counts().await.contains_key("s1"));
+
+		router.unregister_input("s1").await;
+		assert!(!router.subscriber_counts().await.contains_key("s1"));
+	}
+
+	#[tokio::test]
+	async fn unregister_keeps_channel_with_active_subscriber() {
+		let router = StreamRouter::new();
+		router.register_input("s1", 8).await;
+		let _rx = router.subscribe("s1", 8).await;
+
+		router.unregister_input("s1").await;
+		assert!(router.subscriber_counts().await.contains_key("s1"));
+	}
+
+	#[tokio::test]
+	async fn all_inputs_must_unregister_before_channel_drops() {
+		let router = StreamRouter::new();
+		router.register_input("s1", 8).await;
+		router.register_input("s1", 8).await;
+
+		router.unregister_input("s1").await;
+		assert!(router.subscriber_counts().await.contains_key("s1"));
+
+		router.unregister_input("s1").await;
+		assert!(!router.subscriber_counts().await.contains_key("s1"));
+	}
+
+	#[tokio::test]
+	async fn subscribe_lazily_creates_channel_without_an_input() {
+		let router = StreamRouter::new();
+		let _rx = router.subscribe("s1", 8).await;
+		assert_eq!(router.subscriber_counts().await.get("s1"), Some(&1));
+	}
+
+	#[tokio::test]
+	async fn unsubscribe_drops_channel_once_idle() {
+		let router = StreamRouter::new();
+		let rx = router.subscribe("s1", 8).await;
+		drop(rx);
+
+		router.unsubscribe("s1").await;
+		assert!(!router.subscriber_counts().await.contains_key("s1"));
+	}
+
+	#[tokio::test]
+	async fn unsubscribe_keeps_channel_with_active_input() {
+		let router = StreamRouter::new();
+		router.register_input("s1", 8).await;
+		let rx = router.subscribe("s1", 8).await;
+		drop(rx);
+
+		router.unsubscribe("s1").await;
+		assert!(router.subscriber_counts().await.contains_key("s1"));
+	}
+}
@@ -0,0 +1,195 @@
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::time::Duration;
+
+/// How a subscriber's output task should react once its broadcast receiver
+/// has lagged (the sender outran the channel capacity and dropped packets
+/// before this subscriber read them).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropPolicy {
+	/// Resync to the newest packet (the broadcast channel already does this)
+	/// and log the discontinuity, rather than disconnecting the subscriber.
+	/// Nothing is injected into the payload stream: a marker isn't a valid
+	/// media packet, and writing one inline would corrupt the subscriber's
+	/// demux exactly as the lag already has, just in a different way.
+	Resync,
+	/// Disconnect the subscriber once a single lag event drops at least
+	/// `threshold` packets, instead of limping along on a stream it can't
+	/// keep up with.
+	DisconnectOnLag { threshold: u64 },
+}
+
+/// Per-stream tuning: the broadcast channel's capacity, the SRT latency to
+/// negotiate, and how subscribers that fall behind should be handled.
+///
+/// `latency` only takes effect for `call://` connections, which dial out and
+/// can negotiate per-connection. `listen://` input and output listeners bind
+/// once with [`SettingsConfig::default_settings`]'s latency before any
+/// StreamID is known, so a per-stream override is a no-op there; the relay
+/// logs a warning on accept when a stream's configured latency would have
+/// differed.
+#[derive(Debug, Clone, Copy)]
+pub struct StreamSettings {
+	pub capacity: usize,
+	pub latency: Duration,
+	pub drop_policy: DropPolicy,
+}
+
+impl Default for StreamSettings {
+	fn default() -> Self {
+		Self { capacity: 1024, latency: Duration::from_millis(120), drop_policy: DropPolicy::Resync }
+	}
+}
+
+/// Per-stream settings loaded from a config file, falling back to a default
+/// for any StreamID without its own entry. Each non-empty, non-comment line
+/// is `<stream_id> [capacity=<n>] [latency_ms=<n>] [drop_policy=resync|disconnect:<threshold>]`,
+/// with `default` used in place of `<stream_id>` to override the fallback.
+/// See [`StreamSettings`] for the caveat on `latency_ms` in `listen://` mode.
+pub struct SettingsConfig {
+	default: StreamSettings,
+	per_stream: HashMap<String, StreamSettings>,
+}
+
+impl SettingsConfig {
+	pub fn load(path: &str) -> Result<Self> {
+		let contents = fs::read_to_string(path).with_context(|| format!("Failed to read settings config: {path}"))?;
+
+		let mut config = Self { default: StreamSettings::default(), per_stream: HashMap::new() };
+
+		for (i, line) in contents.lines().enumerate() {
+			let line = line.trim();
+			if line.is_empty() || line.starts_with('#') {
+				continue;
+			}
+
+			let mut parts = line.split_whitespace();
+			let key = parts.next().with_context(|| format!("{path}:{}: missing stream id", i + 1))?;
+			let mut settings = StreamSettings::default();
+
+			for field in parts {
+				let (name, value) = field.split_once('=').with_context(|| format!("{path}:{}: expected key=value, got '{}'", i + 1, field))?;
+				match name {
+					"capacity" => {
+						let capacity: usize = value.parse().with_context(|| format!("{path}:{}: invalid capacity '{}'", i + 1, value))?;
+						if capacity == 0 {
+							bail!("{path}:{}: capacity must be at least 1, got '0'", i + 1);
+						}
+						settings.capacity = capacity;
+					}
+					"latency_ms" => {
+						settings.latency = Duration::from_millis(value.parse().with_context(|| format!("{path}:{}: invalid latency_ms '{}'", i + 1, value))?)
+					}
+					"drop_policy" => settings.drop_policy = parse_drop_policy(path, i, value)?,
+					other => bail!("{path}:{}: unknown setting '{}'", i + 1, other),
+				}
+			}
+
+			if key == "default" {
+				config.default = settings;
+			} else {
+				config.per_stream.insert(key.to_string(), settings);
+			}
+		}
+
+		Ok(config)
+	}
+
+	/// Returns the settings to use for `stream_id`, falling back to the
+	/// configured (or built-in) default when it has no entry of its own.
+	pub fn for_stream(&self, stream_id: &str) -> StreamSettings {
+		self.per_stream.get(stream_id).copied().unwrap_or(self.default)
+	}
+
+	/// Returns the fallback settings, used to configure a listener before any
+	/// connection (and therefore its StreamID) has been seen.
+	pub fn default_settings(&self) -> StreamSettings {
+		self.default
+	}
+}
+
+impl Default for SettingsConfig {
+	fn default() -> Self {
+		Self { default: StreamSettings::default(), per_stream: HashMap::new() }
+	}
+}
+
+fn parse_drop_policy(path: &str, line_no: usize, value: &str) -> Result<DropPolicy> {
+	if value == "resync" {
+		return Ok(DropPolicy::Resync);
+	}
+
+	if let Some(threshold) = value.strip_prefix("disconnect:") {
+		let threshold = threshold.parse().with_context(|| format!("{path}:{}: invalid disconnect threshold '{}'", line_no + 1, threshold))?;
+		return Ok(DropPolicy::DisconnectOnLag { threshold });
+	}
+
+	bail!("{path}:{}: invalid drop_policy '{}', expected 'resync' or 'disconnect:<threshold>'", line_no + 1, value)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn write_config(name: &str, contents: &str) -> String {
+		let path = std::env::temp_dir().join(format!("srt-relay-settings-test-{name}-{}.conf", std::process::id()));
+		fs::write(&path, contents).unwrap();
+		path.to_str().unwrap().to_string()
+	}
+
+	#[test]
+	fn loads_per_stream_overrides_and_default() {
+		let path = write_config(
+			"basic",
+			"# comment\n\ndefault capacity=2048 latency_ms=200\ncam1 capacity=512 drop_policy=disconnect:50\n",
+		);
+		let config = SettingsConfig::load(&path).unwrap();
+
+		assert_eq!(config.default_settings().capacity, 2048);
+		assert_eq!(config.default_settings().latency, Duration::from_millis(200));
+
+		let cam1 = config.for_stream("cam1");
+		assert_eq!(cam1.capacity, 512);
+		assert_eq!(cam1.drop_policy, DropPolicy::DisconnectOnLag { threshold: 50 });
+
+		let other = config.for_stream("unconfigured");
+		assert_eq!(other.capacity, 2048);
+
+		fs::remove_file(path).unwrap();
+	}
+
+	#[test]
+	fn rejects_zero_capacity() {
+		let path = write_config("zerocap", "default capacity=0\n");
+		assert!(SettingsConfig::load(&path).is_err());
+		fs::remove_file(path).unwrap();
+	}
+
+	#[test]
+	fn rejects_unknown_setting() {
+		let path = write_config("unknown", "cam1 bogus=1\n");
+		assert!(SettingsConfig::load(&path).is_err());
+		fs::remove_file(path).unwrap();
+	}
+
+	#[test]
+	fn parse_drop_policy_accepts_resync() {
+		assert_eq!(parse_drop_policy("test.conf", 0, "resync").unwrap(), DropPolicy::Resync);
+	}
+
+	#[test]
+	fn parse_drop_policy_accepts_disconnect_threshold() {
+		assert_eq!(parse_drop_policy("test.conf", 0, "disconnect:10").unwrap(), DropPolicy::DisconnectOnLag { threshold: 10 });
+	}
+
+	#[test]
+	fn parse_drop_policy_rejects_unknown_value() {
+		assert!(parse_drop_policy("test.conf", 0, "bogus").is_err());
+	}
+
+	#[test]
+	fn parse_drop_policy_rejects_invalid_threshold() {
+		assert!(parse_drop_policy("test.conf", 0, "disconnect:not-a-number").is_err());
+	}
+}
@@ -0,0 +1,41 @@
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::watch;
+use tracing::info;
+
+/// How long `main` will wait for in-flight connections to drain after a
+/// shutdown signal before giving up and returning anyway.
+pub const DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Broadcasts a one-shot shutdown notification to every listener/connection
+/// task, via a `watch` channel that flips from `false` to `true`.
+#[derive(Clone)]
+pub struct Shutdown {
+	tx: watch::Sender<bool>,
+}
+
+impl Shutdown {
+	/// Creates a new shutdown signal along with a receiver that tasks can
+	/// clone from to observe it.
+	pub fn new() -> (Self, watch::Receiver<bool>) {
+		let (tx, rx) = watch::channel(false);
+		(Self { tx }, rx)
+	}
+
+	/// Waits for SIGINT or SIGTERM, then notifies every receiver.
+	pub async fn listen(self) {
+		let mut sigterm = signal(SignalKind::terminate()).expect("Failed to install SIGTERM handler");
+		let mut sigint = signal(SignalKind::interrupt()).expect("Failed to install SIGINT handler");
+
+		tokio::select! {
+			_ = sigterm.recv() => info!("Received SIGTERM, shutting down"),
+			_ = sigint.recv() => info!("Received SIGINT, shutting down"),
+		}
+
+		let _ = self.tx.send(true);
+	}
+}
+
+/// Returns true once a shutdown has been signalled, without blocking.
+pub fn is_shutdown(rx: &watch::Receiver<bool>) -> bool {
+	*rx.borrow()
+}